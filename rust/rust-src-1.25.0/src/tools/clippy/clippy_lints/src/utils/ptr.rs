@@ -1,10 +1,10 @@
 use std::borrow::Cow;
+use std::ops::ControlFlow;
 use rustc::hir::*;
-use rustc::hir::intravisit::{walk_expr, NestedVisitorMap, Visitor};
 use rustc::lint::LateContext;
-use syntax::ast::Name;
 use syntax::codemap::Span;
-use utils::{get_pat_name, match_var, snippet};
+use utils::snippet;
+use utils::visitors::for_each_expr;
 
 pub fn get_spans(
     cx: &LateContext,
@@ -13,8 +13,8 @@ pub fn get_spans(
     replacements: &'static [(&'static str, &'static str)],
 ) -> Option<Vec<(Span, Cow<'static, str>)>> {
     if let Some(body) = opt_body_id.map(|id| cx.tcx.hir.body(id)) {
-        get_binding_name(&body.arguments[idx])
-            .map_or_else(|| Some(vec![]), |name| extract_clone_suggestions(cx, name, replacements, body))
+        get_binding_hir_id(&body.arguments[idx])
+            .map_or_else(|| Some(vec![]), |hir_id| extract_clone_suggestions(cx, hir_id, replacements, body))
     } else {
         Some(vec![])
     }
@@ -22,62 +22,59 @@ pub fn get_spans(
 
 fn extract_clone_suggestions<'a, 'tcx: 'a>(
     cx: &LateContext<'a, 'tcx>,
-    name: Name,
+    hir_id: HirId,
     replace: &'static [(&'static str, &'static str)],
     body: &'tcx Body,
 ) -> Option<Vec<(Span, Cow<'static, str>)>> {
-    let mut visitor = PtrCloneVisitor {
-        cx,
-        name,
-        replace,
-        spans: vec![],
-        abort: false,
-    };
-    visitor.visit_body(body);
-    if visitor.abort {
-        None
-    } else {
-        Some(visitor.spans)
-    }
-}
-
-struct PtrCloneVisitor<'a, 'tcx: 'a> {
-    cx: &'a LateContext<'a, 'tcx>,
-    name: Name,
-    replace: &'static [(&'static str, &'static str)],
-    spans: Vec<(Span, Cow<'static, str>)>,
-    abort: bool,
-}
-
-impl<'a, 'tcx: 'a> Visitor<'tcx> for PtrCloneVisitor<'a, 'tcx> {
-    fn visit_expr(&mut self, expr: &'tcx Expr) {
-        if self.abort {
-            return;
-        }
+    let mut spans = vec![];
+    let aborted = for_each_expr(cx, body, |expr| {
         if let ExprMethodCall(ref seg, _, ref args) = expr.node {
-            if args.len() == 1 && match_var(&args[0], self.name) {
+            // `args[0]` is the receiver; bail out on trailing args, we can't rewrite those.
+            let receiver = &args[0];
+            if args.len() == 1 && path_to_local_id(receiver, hir_id) {
                 if seg.name == "capacity" {
-                    self.abort = true;
-                    return;
+                    return ControlFlow::Break(());
                 }
-                for &(fn_name, suffix) in self.replace {
+                for &(fn_name, suffix) in replace {
                     if seg.name == fn_name {
-                        self.spans
-                            .push((expr.span, snippet(self.cx, args[0].span, "_") + suffix));
-                        return;
+                        spans.push((expr.span, snippet(cx, receiver.span, "_") + suffix));
+                        break;
                     }
                 }
             }
-            return;
         }
-        walk_expr(self, expr);
+        ControlFlow::Continue(())
+    });
+    if aborted.is_some() {
+        None
+    } else {
+        Some(spans)
+    }
+}
+
+/// Peels off any `&`/`&mut` patterns to get at the underlying binding.
+fn strip_pat_refs(pat: &Pat) -> &Pat {
+    if let PatKind::Ref(ref subpat, _) = pat.node {
+        strip_pat_refs(subpat)
+    } else {
+        pat
     }
+}
 
-    fn nested_visit_map<'this>(&'this mut self) -> NestedVisitorMap<'this, 'tcx> {
-        NestedVisitorMap::None
+fn get_binding_hir_id(arg: &Arg) -> Option<HirId> {
+    if let PatKind::Binding(_, hir_id, _, _) = strip_pat_refs(&arg.pat).node {
+        Some(hir_id)
+    } else {
+        None
     }
 }
 
-fn get_binding_name(arg: &Arg) -> Option<Name> {
-    get_pat_name(&arg.pat)
+/// Checks whether `expr` is a path that resolves to the local binding `hir_id`.
+fn path_to_local_id(expr: &Expr, hir_id: HirId) -> bool {
+    if let ExprPath(QPath::Resolved(None, ref path)) = expr.node {
+        if let Res::Local(id) = path.res {
+            return id == hir_id;
+        }
+    }
+    false
 }
@@ -0,0 +1,54 @@
+use std::ops::ControlFlow;
+use rustc::hir::*;
+use rustc::hir::intravisit::{walk_expr, NestedVisitorMap, Visitor};
+use rustc::lint::LateContext;
+
+/// Walks every expression reachable from `body`, calling `f` on each one. As soon as `f`
+/// returns `ControlFlow::Break`, the walk stops and that value is returned; if the walk
+/// finishes without ever breaking, `None` is returned.
+///
+/// This descends into closures, so a single call covers both the top-level expressions of
+/// `body` and anything defined inside a closure within it.
+pub fn for_each_expr<'tcx, B>(
+    cx: &LateContext<'_, 'tcx>,
+    body: &'tcx Body,
+    f: impl FnMut(&'tcx Expr) -> ControlFlow<B>,
+) -> Option<B> {
+    struct ExprVisitor<'a, 'tcx, B, F> {
+        cx: &'a LateContext<'a, 'tcx>,
+        f: F,
+        result: Option<B>,
+    }
+
+    impl<'a, 'tcx, B, F> Visitor<'tcx> for ExprVisitor<'a, 'tcx, B, F>
+    where
+        F: FnMut(&'tcx Expr) -> ControlFlow<B>,
+    {
+        fn visit_expr(&mut self, expr: &'tcx Expr) {
+            if self.result.is_some() {
+                return;
+            }
+            match (self.f)(expr) {
+                ControlFlow::Break(b) => {
+                    self.result = Some(b);
+                    return;
+                },
+                ControlFlow::Continue(()) => {},
+            }
+            if let ExprClosure(_, _, body_id, _, _) = expr.node {
+                let body = self.cx.tcx.hir.body(body_id);
+                self.visit_expr(&body.value);
+                return;
+            }
+            walk_expr(self, expr);
+        }
+
+        fn nested_visit_map<'this>(&'this mut self) -> NestedVisitorMap<'this, 'tcx> {
+            NestedVisitorMap::None
+        }
+    }
+
+    let mut visitor = ExprVisitor { cx, f, result: None };
+    visitor.visit_expr(&body.value);
+    visitor.result
+}
@@ -0,0 +1,29 @@
+#![warn(clippy::ptr_arg)]
+
+fn no_clone_suggestion_on_shadowed_name(s: &String) {
+    // `s` is rebound before any `.clone()` call, so the outer parameter is never cloned and
+    // no suggestion should be produced for it.
+    let s = s.to_uppercase();
+    s.clone();
+}
+
+fn finds_clone_inside_closure(p: &String) -> Vec<String> {
+    // The only use of `p` is a `.clone()` buried inside a closure passed to `map`; it must
+    // still be found and turned into a suggested replacement.
+    (0..1).map(|_| p.clone()).collect()
+}
+
+fn finds_clone_as_receiver(p: &String) -> String {
+    // `p` is the receiver of `.clone()`, not an argument to some other call; this must be
+    // matched independently of any wrapping call expression.
+    p.clone()
+}
+
+fn does_not_fire_when_capacity_called_in_closure(p: &String) {
+    // `.capacity()` appears only inside a closure; the abort-on-`capacity` check must still
+    // apply there, so the later `.clone()` should not produce a suggestion either.
+    (0..1).for_each(|_| {
+        p.capacity();
+    });
+    p.clone();
+}